@@ -4,15 +4,51 @@ use bytes::Buf;
 use ctx::AppConfig;
 use db::Db;
 use hyper::{header, Body, Request, Response, StatusCode};
+use chrono::Utc;
+use ipnet::IpNet;
 use redis::FromRedisValue;
 use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
 
 pub(crate) const DB_STATUS_LIST: &str = "status_list";
+/// Hash holding CIDR-scoped statuses, keyed by network (`203.0.113.0/24`). Exact IPs live in
+/// [`DB_STATUS_LIST`]; networks are stored separately so lookups can do a longest-prefix match.
+pub(crate) const DB_STATUS_CIDR_LIST: &str = "status_cidr_list";
+/// Key prefix for temporary, self-expiring statuses. Each lives as its own keyed value
+/// (`status_expiring:<ip>`) under a Redis TTL, so elapsed bans lift themselves without cleanup.
+pub(crate) const DB_STATUS_EXPIRING_PREFIX: &str = "status_expiring";
+/// Append-only list recording each enforcement decision for later audit.
+pub(crate) const DB_STATUS_LOG: &str = "status_log";
+
+/// Whether an `IpStatusPayload` IP field is a CIDR block rather than a single address.
+fn is_cidr(ip: &str) -> bool {
+    ip.contains('/')
+}
+
+/// Redis key under which a temporary status for `ip` is stored.
+fn expiring_key(ip: &str) -> String {
+    format!("{}:{}", DB_STATUS_EXPIRING_PREFIX, ip)
+}
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub(crate) struct IpStatusPayload {
     pub(crate) ip: String,
     pub(crate) status: i8,
+    /// Unix timestamp (seconds) at which the status lapses. When set, the entry is stored as a
+    /// self-expiring key rather than in the permanent hash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) expires_at: Option<i64>,
+}
+
+/// A single audit-trail record of an enforcement decision.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub(crate) struct IpStatusLogEntry {
+    pub(crate) ip: String,
+    pub(crate) status: i8,
+    /// The rule that decided the outcome: `expiring:<ip>`, `cidr:<network>`, `exact:<ip>`, or
+    /// `default` when nothing matched.
+    pub(crate) matched_rule: String,
+    pub(crate) timestamp: i64,
 }
 
 pub(crate) async fn post_ip_status(
@@ -35,11 +71,14 @@ pub(crate) async fn get_ip_status_list(cfg: &AppConfig) -> GenericResult<Respons
     let mut db = Db::create_instance(cfg).await;
     let list = db.read_ip_status_list().await;
 
+    let now = Utc::now().timestamp();
     let list: Vec<IpStatusPayload> = list
         .iter()
         .map(|v| IpStatusPayload {
             ip: v.0.clone(),
             status: v.1,
+            // Surface the absolute expiry (now + remaining TTL) for temporary entries.
+            expires_at: v.2.map(|ttl| now + ttl),
         })
         .collect();
     let serialized = serde_json::to_string(&list)?;
@@ -50,16 +89,59 @@ pub(crate) async fn get_ip_status_list(cfg: &AppConfig) -> GenericResult<Respons
         .body(Body::from(serialized))?)
 }
 
+/// Resolves the status of `ip`, records the decision to the audit trail, and returns the action
+/// the caller must take. `Blocked` → `403 Forbidden`, `Trusted` → skip the security checks,
+/// `None` → run the normal [`SignedMessage::verify_message`] flow.
+pub(crate) async fn enforce_ip_status(
+    cfg: &AppConfig,
+    ip: String,
+) -> GenericResult<IpEnforcement> {
+    let mut db = Db::create_instance(cfg).await;
+    let (status, matched_rule) = db.read_ip_status(ip.clone()).await;
+
+    let entry = IpStatusLogEntry {
+        ip,
+        status: status.to_i8(),
+        matched_rule,
+        timestamp: Utc::now().timestamp(),
+    };
+    db.append_ip_status_log(&entry).await?;
+
+    Ok(status.enforcement())
+}
+
+pub(crate) async fn get_ip_status_log(cfg: &AppConfig) -> GenericResult<Response<Body>> {
+    let mut db = Db::create_instance(cfg).await;
+    let log = db.read_ip_status_log().await;
+    let serialized = serde_json::to_string(&log)?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::from(serialized))?)
+}
+
 #[derive(Debug, Deserialize, PartialEq)]
 pub(crate) enum IpStatus {
-    /// Follow the normal procedure.
+    /// Follow the normal procedure (run the signature/security checks).
     None = -1,
-    /// Means incoming request will be respond as `403 Forbidden`.
-    Trusted,
     /// Means incoming request will bypass the security checks on the middleware layer.
+    Trusted,
+    /// Means incoming request will be responded to with `403 Forbidden`.
     Blocked,
 }
 
+/// The action the middleware takes for a resolved [`IpStatus`].
+#[derive(Debug, PartialEq)]
+pub(crate) enum IpEnforcement {
+    /// Reject the request with `403 Forbidden` (`Blocked`).
+    Forbidden,
+    /// Let the request through without running the security checks (`Trusted`).
+    SkipSecurityChecks,
+    /// Run the normal [`SignedMessage::verify_message`] flow (`None`).
+    RunSecurityChecks,
+}
+
 impl IpStatus {
     pub(crate) fn from_i8(value: i8) -> Self {
         match value {
@@ -68,6 +150,22 @@ impl IpStatus {
             _ => Self::None,
         }
     }
+
+    pub(crate) fn to_i8(&self) -> i8 {
+        match self {
+            Self::None => -1,
+            Self::Trusted => 0,
+            Self::Blocked => 1,
+        }
+    }
+
+    pub(crate) fn enforcement(&self) -> IpEnforcement {
+        match self {
+            Self::Blocked => IpEnforcement::Forbidden,
+            Self::Trusted => IpEnforcement::SkipSecurityChecks,
+            Self::None => IpEnforcement::RunSecurityChecks,
+        }
+    }
 }
 
 impl FromRedisValue for IpStatus {
@@ -82,8 +180,10 @@ impl FromRedisValue for IpStatus {
 pub(crate) trait IpStatusOperations {
     async fn insert_ip_status(&mut self, ip: String, status: IpStatus) -> GenericResult<()>;
     async fn bulk_insert_ip_status(&mut self, payload: Vec<IpStatusPayload>) -> GenericResult<()>;
-    async fn read_ip_status(&mut self, ip: String) -> IpStatus;
-    async fn read_ip_status_list(&mut self) -> Vec<(String, i8)>;
+    async fn read_ip_status(&mut self, ip: String) -> (IpStatus, String);
+    async fn read_ip_status_list(&mut self) -> Vec<(String, i8, Option<i64>)>;
+    async fn append_ip_status_log(&mut self, entry: &IpStatusLogEntry) -> GenericResult<()>;
+    async fn read_ip_status_log(&mut self) -> Vec<IpStatusLogEntry>;
 }
 
 #[async_trait]
@@ -98,29 +198,171 @@ impl IpStatusOperations for Db {
 
     async fn bulk_insert_ip_status(&mut self, payload: Vec<IpStatusPayload>) -> GenericResult<()> {
         let mut pipe = redis::pipe();
-        let formatted: Vec<(String, i8)> =
-            payload.iter().map(|v| (v.ip.clone(), v.status)).collect();
-        pipe.hset_multiple(DB_STATUS_LIST, &formatted);
+
+        // Temporary entries become self-expiring keys; the rest split into CIDR and exact hashes.
+        let (expiring, permanent): (Vec<_>, Vec<_>) =
+            payload.into_iter().partition(|v| v.expires_at.is_some());
+
+        for entry in &expiring {
+            // Expiring entries are stored as single keyed values and `read_ip_status` only does an
+            // exact `GET` on them, so a CIDR here would be silently stored and never enforced.
+            if is_cidr(&entry.ip) {
+                return Err(format!(
+                    "Expiring statuses must be exact IPs, not CIDR ranges: `{}`",
+                    entry.ip
+                )
+                .into());
+            }
+            let key = expiring_key(&entry.ip);
+            pipe.set(&key, entry.status).ignore();
+            // Absolute expiry so the ban lifts itself without a sweep.
+            pipe.expire_at(&key, entry.expires_at.unwrap_or_default() as usize)
+                .ignore();
+        }
+
+        let (cidrs, exacts): (Vec<_>, Vec<_>) =
+            permanent.into_iter().partition(|v| is_cidr(&v.ip));
+        if !exacts.is_empty() {
+            let formatted: Vec<(String, i8)> =
+                exacts.iter().map(|v| (v.ip.clone(), v.status)).collect();
+            pipe.hset_multiple(DB_STATUS_LIST, &formatted);
+        }
+        if !cidrs.is_empty() {
+            let formatted: Vec<(String, i8)> =
+                cidrs.iter().map(|v| (v.ip.clone(), v.status)).collect();
+            pipe.hset_multiple(DB_STATUS_CIDR_LIST, &formatted);
+        }
         pipe.query_async(&mut self.connection).await?;
 
         Ok(())
     }
 
-    async fn read_ip_status(&mut self, ip: String) -> IpStatus {
-        redis::cmd("HGET")
+    async fn read_ip_status(&mut self, ip: String) -> (IpStatus, String) {
+        // A live temporary status wins; Redis has already purged it if elapsed.
+        let expiring: Option<i8> = redis::cmd("GET")
+            .arg(expiring_key(&ip))
+            .query_async(&mut self.connection)
+            .await
+            .unwrap_or(None);
+        if let Some(status) = expiring {
+            return (IpStatus::from_i8(status), format!("expiring:{}", ip));
+        }
+
+        // Then prefer a CIDR match (longest prefix wins), then an exact entry, then `None`.
+        if let Ok(addr) = ip.parse::<IpAddr>() {
+            let networks: Vec<(String, i8)> = redis::cmd("HGETALL")
+                .arg(DB_STATUS_CIDR_LIST)
+                .query_async(&mut self.connection)
+                .await
+                .unwrap_or_default();
+
+            let mut best: Option<(u8, i8, String)> = None;
+            for (net, status) in networks {
+                if let Ok(network) = net.parse::<IpNet>() {
+                    if network.contains(&addr)
+                        && best.as_ref().map_or(true, |(len, _, _)| network.prefix_len() > *len)
+                    {
+                        best = Some((network.prefix_len(), status, net));
+                    }
+                }
+            }
+
+            if let Some((_, status, net)) = best {
+                return (IpStatus::from_i8(status), format!("cidr:{}", net));
+            }
+        }
+
+        let exact: Option<i8> = redis::cmd("HGET")
             .arg(DB_STATUS_LIST)
-            .arg(ip)
+            .arg(&ip)
             .query_async(&mut self.connection)
             .await
-            .unwrap_or(IpStatus::None)
+            .unwrap_or(None);
+        match exact {
+            Some(status) => (IpStatus::from_i8(status), format!("exact:{}", ip)),
+            None => (IpStatus::None, String::from("default")),
+        }
     }
 
-    async fn read_ip_status_list(&mut self) -> Vec<(String, i8)> {
-        redis::cmd("HGETALL")
-            .arg(DB_STATUS_LIST)
+    async fn read_ip_status_list(&mut self) -> Vec<(String, i8, Option<i64>)> {
+        // Permanent exact and CIDR entries carry no TTL.
+        let mut list: Vec<(String, i8, Option<i64>)> = Vec::new();
+        for hash in [DB_STATUS_LIST, DB_STATUS_CIDR_LIST] {
+            let entries: Vec<(String, i8)> = redis::cmd("HGETALL")
+                .arg(hash)
+                .query_async(&mut self.connection)
+                .await
+                .unwrap_or_default();
+            list.extend(entries.into_iter().map(|(ip, status)| (ip, status, None)));
+        }
+
+        // Expiring entries report their remaining TTL in seconds. Enumerate them with a cursored
+        // `SCAN` rather than `KEYS`, which blocks the whole server on large keyspaces.
+        let mut keys: Vec<String> = Vec::new();
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, batch): (u64, Vec<String>) = match redis::cmd("SCAN")
+                .arg(cursor)
+                .arg("MATCH")
+                .arg(format!("{}:*", DB_STATUS_EXPIRING_PREFIX))
+                .arg("COUNT")
+                .arg(100)
+                .query_async(&mut self.connection)
+                .await
+            {
+                Ok(res) => res,
+                Err(_) => break,
+            };
+            keys.extend(batch);
+            cursor = next;
+            if cursor == 0 {
+                break;
+            }
+        }
+        for key in keys {
+            let status: Option<i8> = redis::cmd("GET")
+                .arg(&key)
+                .query_async(&mut self.connection)
+                .await
+                .unwrap_or(None);
+            let ttl: i64 = redis::cmd("TTL")
+                .arg(&key)
+                .query_async(&mut self.connection)
+                .await
+                .unwrap_or(-1);
+            if let Some(status) = status {
+                let ip = key
+                    .strip_prefix(&format!("{}:", DB_STATUS_EXPIRING_PREFIX))
+                    .unwrap_or(&key)
+                    .to_string();
+                list.push((ip, status, (ttl >= 0).then_some(ttl)));
+            }
+        }
+
+        list
+    }
+
+    async fn append_ip_status_log(&mut self, entry: &IpStatusLogEntry) -> GenericResult<()> {
+        let serialized = serde_json::to_string(entry)?;
+        Ok(redis::cmd("RPUSH")
+            .arg(DB_STATUS_LOG)
+            .arg(serialized)
+            .query_async(&mut self.connection)
+            .await?)
+    }
+
+    async fn read_ip_status_log(&mut self) -> Vec<IpStatusLogEntry> {
+        let raw: Vec<String> = redis::cmd("LRANGE")
+            .arg(DB_STATUS_LOG)
+            .arg(0)
+            .arg(-1)
             .query_async(&mut self.connection)
             .await
-            .unwrap_or_default()
+            .unwrap_or_default();
+
+        raw.iter()
+            .filter_map(|v| serde_json::from_str(v).ok())
+            .collect()
     }
 }
 
@@ -142,6 +384,7 @@ fn test_ip_status_serialzation_and_deserialization() {
     let expected_ip_status = IpStatusPayload {
         ip: String::from("127.0.0.1"),
         status: 0,
+        expires_at: None,
     };
 
     assert_eq!(actual_ip_status, expected_ip_status);
@@ -159,6 +402,61 @@ fn test_if_ip_status_values_same_as_before() {
     assert_eq!(IpStatus::Blocked, IpStatus::from_i8(1));
 }
 
+#[test]
+fn test_is_cidr() {
+    assert!(is_cidr("203.0.113.0/24"));
+    assert!(is_cidr("2001:db8::/32"));
+    assert!(!is_cidr("203.0.113.7"));
+    assert!(!is_cidr("::1"));
+}
+
+#[test]
+fn test_ip_status_enforcement() {
+    assert_eq!(IpStatus::Blocked.enforcement(), IpEnforcement::Forbidden);
+    assert_eq!(
+        IpStatus::Trusted.enforcement(),
+        IpEnforcement::SkipSecurityChecks
+    );
+    assert_eq!(
+        IpStatus::None.enforcement(),
+        IpEnforcement::RunSecurityChecks
+    );
+}
+
+#[test]
+fn test_ip_status_log_entry_roundtrip() {
+    let entry = IpStatusLogEntry {
+        ip: String::from("203.0.113.7"),
+        status: 1,
+        matched_rule: String::from("blocked"),
+        timestamp: 1_700_000_000,
+    };
+
+    let serialized = serde_json::to_string(&entry).unwrap();
+    let deserialized: IpStatusLogEntry = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(entry, deserialized);
+}
+
+#[test]
+fn test_expiring_key() {
+    assert_eq!(expiring_key("203.0.113.7"), "status_expiring:203.0.113.7");
+}
+
+#[test]
+fn test_cidr_longest_prefix_match() {
+    use std::str::FromStr;
+
+    let addr = IpAddr::from_str("203.0.113.7").unwrap();
+    let wide = IpNet::from_str("203.0.113.0/24").unwrap();
+    let narrow = IpNet::from_str("203.0.113.0/28").unwrap();
+    let other = IpNet::from_str("198.51.100.0/24").unwrap();
+
+    assert!(wide.contains(&addr));
+    assert!(narrow.contains(&addr));
+    assert!(!other.contains(&addr));
+    assert!(narrow.prefix_len() > wide.prefix_len());
+}
+
 #[test]
 fn test_from_redis_value() {
     let redis_val = redis::Value::Int(-1);